@@ -0,0 +1,35 @@
+use std::fmt;
+use std::io;
+
+/// Distinguishes the ways a box tree can fail to parse: data that violates
+/// the box grammar, a box type this parser doesn't support yet, a read that
+/// ran past the available bytes, and plain I/O failures.
+#[derive(Debug)]
+pub enum Error {
+    InvalidData(String),
+    Unsupported(String),
+    UnexpectedEof { wanted: u64, at: u64, limit: u64 },
+    Io(io::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::InvalidData(msg) => write!(f, "invalid data: {}", msg),
+            Error::Unsupported(msg) => write!(f, "unsupported: {}", msg),
+            Error::UnexpectedEof { wanted, at, limit } => write!(
+                f, "unexpected EOF: wanted {} bytes at {}, input length: {}", wanted, at, limit),
+            Error::Io(e) => write!(f, "I/O error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
@@ -0,0 +1,456 @@
+use std::cell::RefCell;
+use std::io::Cursor;
+use std::io::Read;
+use std::io::Seek;
+use std::io::SeekFrom;
+use std::rc::Rc;
+
+use crate::error::Error;
+use crate::error::Result;
+
+pub enum Endianness {
+    Big,
+    Little,
+}
+
+pub struct Input<T: Read + Seek> {
+    reader: Rc<RefCell<T>>,
+    offset: u64,
+    limit: u64,
+    cursor: u64,
+}
+
+impl<T: Read + Seek> Input<T> {
+    pub fn create(mut reader: T) -> Result<Self> {
+        let limit = reader.seek(SeekFrom::End(0))?;
+        reader.seek(SeekFrom::Start(0))?;
+        Ok(Input {
+            reader: Rc::new(RefCell::new(reader)),
+            offset: 0,
+            limit,
+            cursor: 0,
+        })
+    }
+
+    fn seek_to_cursor(&mut self) -> Result<()> {
+        self.reader.borrow_mut().seek(SeekFrom::Start(self.offset + self.cursor))?;
+        Ok(())
+    }
+
+    fn check_bounds(&self, n: u64) -> Result<u64> {
+        let end = self.cursor.checked_add(n).ok_or(Error::UnexpectedEof {
+            wanted: n, at: self.cursor, limit: self.limit,
+        })?;
+        if end > self.limit {
+            return Err(Error::UnexpectedEof { wanted: n, at: self.cursor, limit: self.limit });
+        }
+        Ok(end)
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8> {
+        let end = self.check_bounds(1)?;
+        self.seek_to_cursor()?;
+        let mut buf: [u8; 1] = [0; 1];
+        self.reader.borrow_mut().read_exact(&mut buf)?;
+        self.cursor = end;
+        Ok(buf[0])
+    }
+
+    pub fn read_bytes(&mut self, len: u64) -> Result<Vec<u8>> {
+        let end = self.check_bounds(len)?;
+        self.seek_to_cursor()?;
+        let mut buffer = vec![0u8; len as usize];
+        self.reader.borrow_mut().read_exact(&mut buffer)?;
+        self.cursor = end;
+        Ok(buffer)
+    }
+
+    pub fn read_u16(&mut self, bo: &Endianness) -> Result<u16> {
+        let end = self.check_bounds(2)?;
+        self.seek_to_cursor()?;
+        let mut buf: [u8; 2] = [0; 2];
+        self.reader.borrow_mut().read_exact(&mut buf)?;
+        self.cursor = end;
+        match bo {
+            Endianness::Big => Ok(u16::from_be_bytes(buf)),
+            Endianness::Little => Ok(u16::from_le_bytes(buf))
+        }
+    }
+
+    pub fn read_u32(&mut self, bo: &Endianness) -> Result<u32> {
+        let end = self.check_bounds(4)?;
+        self.seek_to_cursor()?;
+        let mut buf: [u8; 4] = [0; 4];
+        self.reader.borrow_mut().read_exact(&mut buf)?;
+        self.cursor = end;
+        match bo {
+            Endianness::Big => Ok(u32::from_be_bytes(buf)),
+            Endianness::Little => Ok(u32::from_le_bytes(buf))
+        }
+    }
+
+    pub fn read_u64(&mut self, bo: &Endianness) -> Result<u64> {
+        let end = self.check_bounds(8)?;
+        self.seek_to_cursor()?;
+        let mut buf: [u8; 8] = [0; 8];
+        self.reader.borrow_mut().read_exact(&mut buf)?;
+        self.cursor = end;
+        match bo {
+            Endianness::Big => Ok(u64::from_be_bytes(buf)),
+            Endianness::Little => Ok(u64::from_le_bytes(buf))
+        }
+    }
+
+    pub fn read_string(&mut self, len: u64) -> Result<String> {
+        let end = self.check_bounds(len)?;
+        self.seek_to_cursor()?;
+        let mut buffer = String::new();
+        let read = self.reader.borrow_mut().by_ref().take(len).read_to_string(&mut buffer)?;
+        if (read as u64) < len {
+            return Err(Error::UnexpectedEof { wanted: len, at: self.cursor, limit: self.limit });
+        }
+        self.cursor = end;
+        Ok(buffer)
+    }
+
+    pub fn seek(&mut self, pos: u64) -> Result<()> {
+        if pos > self.limit {
+            return Err(Error::UnexpectedEof { wanted: 0, at: pos, limit: self.limit });
+        }
+        self.cursor = pos;
+        Ok(())
+    }
+
+    pub fn ff(&mut self, len: u64) -> Result<()> {
+        // TODO maybe implement with SeekFrom::Current?
+        let pos = self.cursor.checked_add(len).ok_or(Error::UnexpectedEof {
+            wanted: len, at: self.cursor, limit: self.limit,
+        })?;
+        self.seek(pos)
+    }
+
+    /// Returns a child window over the same underlying reader, bounded to
+    /// `[offset+cursor, offset+cursor+len)`. Reads on the child are clamped
+    /// to its own `limit` and never reach into the parent's window.
+    pub fn section(&mut self, len: u64) -> Input<T> {
+        Input {
+            reader: Rc::clone(&self.reader),
+            offset: self.offset + self.cursor,
+            limit: len,
+            cursor: 0,
+        }
+    }
+}
+
+impl Input<Cursor<Vec<u8>>> {
+    /// Builds an `Input` over an in-memory buffer, for callers that already
+    /// have the MP4 data loaded (downloaded segment, `mdat` slice, test
+    /// fixture) without writing it to a temp file first.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        Input::from_cursor(Cursor::new(bytes.to_vec()))
+    }
+
+    pub fn from_cursor(cursor: Cursor<Vec<u8>>) -> Result<Self> {
+        Input::create(cursor)
+    }
+}
+
+impl<T: Read + Seek> Input<T> {
+    /// Strips the box header length from a just-read `box_length`/`box_type`
+    /// pair, flagging a declared length shorter than its own header as
+    /// malformed rather than panicking on subtraction overflow.
+    fn strip_header_length(&self, raw_length: u64, header_len: u64, box_type: &str) -> Result<u64> {
+        raw_length.checked_sub(header_len).ok_or_else(|| Error::InvalidData(format!(
+            "box '{}' declares length {} shorter than its {}-byte header", box_type, raw_length, header_len)))
+    }
+
+    /// Flags a box whose declared body length would read past the end of
+    /// the enclosing window as malformed, rather than letting it silently
+    /// read into (or past) the parent's data.
+    fn check_child_length(&self, box_length: u64, box_type: &str) -> Result<()> {
+        let end = self.cursor.checked_add(box_length).ok_or_else(|| Error::InvalidData(format!(
+            "box '{}' length {} overflows from offset {}", box_type, box_length, self.cursor)))?;
+        if end > self.limit {
+            return Err(Error::InvalidData(format!(
+                "box '{}' length {} at {} exceeds parent window limit {}", box_type, box_length, self.cursor, self.limit)));
+        }
+        Ok(())
+    }
+
+    /// Resolves a box's body length (excluding its header) from its
+    /// already-read `full_length` field, treating `full_length == 0` as
+    /// "extends to the end of the enclosing window" like every other reader
+    /// of this box format.
+    fn resolve_box_length(&self, box_offset: u64, header_len: u64, full_length: u64,
+                          box_type: &str) -> Result<u64> {
+        if full_length == 0 {
+            self.limit.checked_sub(box_offset + header_len).ok_or_else(|| Error::InvalidData(format!(
+                "box '{}' header of {} bytes does not fit in the remaining window", box_type, header_len)))
+        } else {
+            self.strip_header_length(full_length, header_len, box_type)
+        }
+    }
+
+    fn quicktime_scan_for_box(&mut self, name: &str,
+                              uuid: Option<(u64, u64)>) -> Result<Option<Input<T>>> {
+        while self.cursor < self.limit {
+            let box_offset = self.cursor;
+            let raw_length: u64 = self.read_u32(&Endianness::Big)? as u64;
+            let box_type: String = self.read_string(4)?;
+            let mut header_len: u64 = 8;
+            // checking for large box:
+            let full_length = if raw_length == 1 {
+                header_len += 8;
+                self.read_u64(&Endianness::Big)?
+            } else {
+                raw_length
+            };
+            let box_length = self.resolve_box_length(box_offset, header_len, full_length, &box_type)?;
+            self.check_child_length(box_length, &box_type)?;
+            if box_type == name {
+                match uuid {
+                    None => {
+                        let found = self.section(box_length);
+                        self.ff(box_length)?;
+                        return Ok(Some(found));
+                    }
+                    Some(u) => {
+                        let msb = self.read_u64(&Endianness::Big)?;
+                        let lsb = self.read_u64(&Endianness::Big)?;
+                        let remaining = self.strip_header_length(box_length, 16, &box_type)?;
+                        if u.0 == msb && u.1 == lsb {
+                            let found = self.section(remaining);
+                            self.ff(remaining)?;
+                            return Ok(Some(found));
+                        }
+                        self.ff(remaining)?;
+                        continue;
+                    }
+                }
+            }
+            self.ff(box_length)?;
+        }
+        Ok(None)
+    }
+
+    /// Searches for the next box named `box_name` at this window's current
+    /// level, starting from the current cursor. Calling this repeatedly on
+    /// the same `Input` enumerates every sibling with that name, since each
+    /// successful search leaves the cursor positioned right after the box
+    /// it found.
+    pub fn quicktime_search_box(&mut self, box_name: &str) -> Result<Option<Input<T>>> {
+        self.quicktime_scan_for_box(box_name, None)
+    }
+
+    pub fn quicktime_search_uuid_box(&mut self, box_uuid: (u64, u64)) -> Result<Option<Input<T>>> {
+        self.quicktime_scan_for_box("uuid", Some(box_uuid))
+    }
+
+    /// Iterates over every top-level box within the current window, e.g. to
+    /// walk `moov -> trak* -> mdia -> minf -> stbl` and dispatch on
+    /// `box_type` instead of issuing one blind search per atom.
+    pub fn boxes(&mut self) -> BoxIter<'_, T> {
+        BoxIter { input: self }
+    }
+}
+
+/// One top-level box found by [`BoxIter`]. `size` is the length of the box
+/// body, i.e. excluding the box header (and the `uuid` field, if present).
+pub struct BoxEntry {
+    pub box_type: String,
+    pub offset: u64,
+    pub size: u64,
+    pub uuid: Option<(u64, u64)>,
+}
+
+pub struct BoxIter<'i, T: Read + Seek> {
+    input: &'i mut Input<T>,
+}
+
+impl<'i, T: Read + Seek> Iterator for BoxIter<'i, T> {
+    type Item = Result<BoxEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.input.cursor >= self.input.limit {
+            return None;
+        }
+        Some(self.read_entry())
+    }
+}
+
+impl<'i, T: Read + Seek> BoxIter<'i, T> {
+    fn read_entry(&mut self) -> Result<BoxEntry> {
+        let box_offset = self.input.cursor;
+        let raw_length = self.input.read_u32(&Endianness::Big)? as u64;
+        let box_type = self.input.read_string(4)?;
+        let mut header_len: u64 = 8;
+        let full_length = if raw_length == 1 {
+            header_len += 8;
+            self.input.read_u64(&Endianness::Big)?
+        } else {
+            raw_length
+        };
+        let uuid = if box_type == "uuid" {
+            let msb = self.input.read_u64(&Endianness::Big)?;
+            let lsb = self.input.read_u64(&Endianness::Big)?;
+            header_len += 16;
+            Some((msb, lsb))
+        } else {
+            None
+        };
+        let size = self.input.resolve_box_length(box_offset, header_len, full_length, &box_type)?;
+        self.input.check_child_length(size, &box_type)?;
+        self.input.ff(size)?;
+        Ok(BoxEntry { box_type, offset: box_offset, size, uuid })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn push_box(buffer: &mut Vec<u8>, box_type: &[u8; 4], body: &[u8]) {
+        let box_length = (8 + body.len()) as u32;
+        buffer.extend_from_slice(&box_length.to_be_bytes());
+        buffer.extend_from_slice(box_type);
+        buffer.extend_from_slice(body);
+    }
+
+    #[test]
+    fn finds_top_level_box_in_memory() {
+        let mut data = Vec::new();
+        push_box(&mut data, b"ftyp", b"isom");
+        push_box(&mut data, b"moov", b"movedata");
+        let mut input = Input::from_bytes(&data).unwrap();
+
+        let moov = input.quicktime_search_box("moov").unwrap().unwrap();
+        assert_eq!(moov.limit, 8);
+    }
+
+    #[test]
+    fn reads_nested_section_independently_of_parent_cursor() {
+        let mut mvhd = Vec::new();
+        push_box(&mut mvhd, b"mvhd", b"abcd");
+        let mut moov_body = Vec::new();
+        push_box(&mut moov_body, b"trak", b"ignored1");
+        moov_body.extend_from_slice(&mvhd);
+
+        let mut data = Vec::new();
+        push_box(&mut data, b"moov", &moov_body);
+        let mut input = Input::from_bytes(&data).unwrap();
+
+        let mut moov = input.quicktime_search_box("moov").unwrap().unwrap();
+        let mut mvhd_box = moov.quicktime_search_box("mvhd").unwrap().unwrap();
+        let body = mvhd_box.read_string(4).unwrap();
+        assert_eq!(body, "abcd");
+    }
+
+    #[test]
+    fn search_handles_size_zero_extends_to_window_end() {
+        let mut data = Vec::new();
+        // size == 0: box extends to the end of the enclosing window
+        data.extend_from_slice(&0u32.to_be_bytes());
+        data.extend_from_slice(b"mdat");
+        data.extend_from_slice(b"payload!");
+        let mut input = Input::from_bytes(&data).unwrap();
+
+        let mut mdat = input.quicktime_search_box("mdat").unwrap().unwrap();
+        assert_eq!(mdat.read_string(8).unwrap(), "payload!");
+    }
+
+    #[test]
+    fn missing_box_returns_none() {
+        let mut data = Vec::new();
+        push_box(&mut data, b"ftyp", b"isom");
+        let mut input = Input::from_bytes(&data).unwrap();
+
+        let result = input.quicktime_search_box("moov").unwrap();
+        assert!(result.is_none());
+    }
+
+    fn push_uuid_box(buffer: &mut Vec<u8>, uuid: (u64, u64), payload: &[u8]) {
+        let mut body = Vec::new();
+        body.extend_from_slice(&uuid.0.to_be_bytes());
+        body.extend_from_slice(&uuid.1.to_be_bytes());
+        body.extend_from_slice(payload);
+        push_box(buffer, b"uuid", &body);
+    }
+
+    #[test]
+    fn uuid_mismatch_does_not_overshoot_the_next_box() {
+        let mut data = Vec::new();
+        push_uuid_box(&mut data, (1, 2), b"skipme-");
+        push_uuid_box(&mut data, (3, 4), b"target!");
+        let mut input = Input::from_bytes(&data).unwrap();
+
+        let mut found = input.quicktime_search_uuid_box((3, 4)).unwrap().unwrap();
+        assert_eq!(found.read_string(7).unwrap(), "target!");
+    }
+
+    #[test]
+    fn repeated_search_enumerates_siblings_with_the_same_name() {
+        let mut data = Vec::new();
+        push_box(&mut data, b"trak", b"track1--");
+        push_box(&mut data, b"trak", b"track2--");
+        let mut input = Input::from_bytes(&data).unwrap();
+
+        let mut first = input.quicktime_search_box("trak").unwrap().unwrap();
+        assert_eq!(first.read_string(8).unwrap(), "track1--");
+        let mut second = input.quicktime_search_box("trak").unwrap().unwrap();
+        assert_eq!(second.read_string(8).unwrap(), "track2--");
+        assert!(input.quicktime_search_box("trak").unwrap().is_none());
+    }
+
+    #[test]
+    fn boxes_enumerates_every_top_level_box() {
+        let mut data = Vec::new();
+        push_box(&mut data, b"ftyp", b"isom");
+        push_box(&mut data, b"free", b"");
+        push_box(&mut data, b"moov", b"movedata");
+        let mut input = Input::from_bytes(&data).unwrap();
+
+        let found: Vec<(String, u64)> = input.boxes()
+            .map(|entry| entry.unwrap())
+            .map(|entry| (entry.box_type, entry.size))
+            .collect();
+        assert_eq!(found, vec![
+            ("ftyp".to_string(), 4),
+            ("free".to_string(), 0),
+            ("moov".to_string(), 8),
+        ]);
+    }
+
+    #[test]
+    fn boxes_handles_size_zero_extends_to_window_end() {
+        let mut data = Vec::new();
+        // size == 0: box extends to the end of the enclosing window
+        data.extend_from_slice(&0u32.to_be_bytes());
+        data.extend_from_slice(b"mdat");
+        data.extend_from_slice(b"payload!");
+        let mut input = Input::from_bytes(&data).unwrap();
+
+        let entry = input.boxes().next().unwrap().unwrap();
+        assert_eq!(entry.box_type, "mdat");
+        assert_eq!(entry.size, 8);
+    }
+
+    #[test]
+    fn box_length_shorter_than_header_is_invalid_data() {
+        let mut data = Vec::new();
+        data.extend_from_slice(&4u32.to_be_bytes()); // shorter than the 8-byte header
+        data.extend_from_slice(b"free");
+        let mut input = Input::from_bytes(&data).unwrap();
+
+        assert!(matches!(input.quicktime_search_box("free"), Err(Error::InvalidData(_))));
+    }
+
+    #[test]
+    fn box_length_past_parent_window_is_invalid_data() {
+        let mut data = Vec::new();
+        data.extend_from_slice(&1000u32.to_be_bytes()); // declares far more than is present
+        data.extend_from_slice(b"free");
+        let mut input = Input::from_bytes(&data).unwrap();
+
+        assert!(matches!(input.quicktime_search_box("free"), Err(Error::InvalidData(_))));
+    }
+}
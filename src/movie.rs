@@ -0,0 +1,436 @@
+use std::io::Read;
+use std::io::Seek;
+
+use crate::error::Error;
+use crate::error::Result;
+use crate::input::Endianness;
+use crate::input::Input;
+
+struct Mvhd {
+    version: u8,
+    creation_time: u64,
+    modification_time: u64,
+    timescale: u32,
+    duration: u64,
+}
+
+impl Mvhd {
+    fn parse<T: Read + Seek>(mut input: Input<T>) -> Result<Mvhd> {
+        let version = (input.read_u32(&Endianness::Big)? >> 24) as u8;
+        let (creation_time, modification_time, timescale, duration) = if version == 1 {
+            let creation_time = input.read_u64(&Endianness::Big)?;
+            let modification_time = input.read_u64(&Endianness::Big)?;
+            let timescale = input.read_u32(&Endianness::Big)?;
+            let duration = input.read_u64(&Endianness::Big)?;
+            (creation_time, modification_time, timescale, duration)
+        } else {
+            let creation_time = input.read_u32(&Endianness::Big)? as u64;
+            let modification_time = input.read_u32(&Endianness::Big)? as u64;
+            let timescale = input.read_u32(&Endianness::Big)?;
+            let duration = input.read_u32(&Endianness::Big)? as u64;
+            (creation_time, modification_time, timescale, duration)
+        };
+        Ok(Mvhd { version, creation_time, modification_time, timescale, duration })
+    }
+}
+
+struct Tkhd {
+    track_id: u32,
+    width: f32,
+    height: f32,
+}
+
+impl Tkhd {
+    fn parse<T: Read + Seek>(mut input: Input<T>) -> Result<Tkhd> {
+        let version = (input.read_u32(&Endianness::Big)? >> 24) as u8;
+        let track_id = if version == 1 {
+            input.ff(16)?; // creation_time, modification_time (64-bit)
+            let track_id = input.read_u32(&Endianness::Big)?;
+            input.ff(4)?; // reserved
+            input.ff(8)?; // duration (64-bit); the track's duration comes from mdhd instead
+            track_id
+        } else {
+            input.ff(8)?; // creation_time, modification_time (32-bit)
+            let track_id = input.read_u32(&Endianness::Big)?;
+            input.ff(4)?; // reserved
+            input.ff(4)?; // duration (32-bit); the track's duration comes from mdhd instead
+            track_id
+        };
+        input.ff(8)?; // reserved
+        input.ff(8)?; // layer, alternate_group, volume, reserved
+        input.ff(36)?; // matrix
+        let width = input.read_u32(&Endianness::Big)? as f32 / 65536.0;
+        let height = input.read_u32(&Endianness::Big)? as f32 / 65536.0;
+        Ok(Tkhd { track_id, width, height })
+    }
+}
+
+struct Mdhd {
+    timescale: u32,
+    duration: u64,
+    language: String,
+}
+
+impl Mdhd {
+    fn parse<T: Read + Seek>(mut input: Input<T>) -> Result<Mdhd> {
+        let version = (input.read_u32(&Endianness::Big)? >> 24) as u8;
+        let (timescale, duration) = if version == 1 {
+            input.ff(16)?; // creation_time, modification_time (64-bit)
+            let timescale = input.read_u32(&Endianness::Big)?;
+            let duration = input.read_u64(&Endianness::Big)?;
+            (timescale, duration)
+        } else {
+            input.ff(8)?; // creation_time, modification_time (32-bit)
+            let timescale = input.read_u32(&Endianness::Big)?;
+            let duration = input.read_u32(&Endianness::Big)? as u64;
+            (timescale, duration)
+        };
+        let packed_language = input.read_u16(&Endianness::Big)?;
+        Ok(Mdhd { timescale, duration, language: decode_language(packed_language) })
+    }
+}
+
+/// Decodes the ISO 639-2/T language code packed into `mdhd` as three 5-bit
+/// values offset from `0x60`, per the QuickTime file format spec.
+fn decode_language(packed: u16) -> String {
+    let c1 = (((packed >> 10) & 0x1F) as u8 + 0x60) as char;
+    let c2 = (((packed >> 5) & 0x1F) as u8 + 0x60) as char;
+    let c3 = ((packed & 0x1F) as u8 + 0x60) as char;
+    [c1, c2, c3].iter().collect()
+}
+
+/// Common-encryption protection info for a track, parsed from
+/// `stsd -> enca/encv -> sinf` (`schm` for the scheme, `schi -> tenc` for the
+/// default key). `scheme` is a four-character code such as `cenc`/`cbcs`.
+pub struct Encryption {
+    pub scheme: [u8; 4],
+    pub version: u32,
+    pub default_kid: [u8; 16],
+    pub per_sample_iv_size: u8,
+}
+
+/// Fixed-size fields of `AudioSampleEntry`/`VideoSampleEntry` that precede
+/// any child boxes, counted from the start of the sample entry (after its
+/// own 8-byte size+format header).
+const AUDIO_SAMPLE_ENTRY_FIXED_LEN: u64 = 8 + 20;
+const VIDEO_SAMPLE_ENTRY_FIXED_LEN: u64 = 8 + 70;
+
+/// Descends `stsd` looking for a protected (`enca`/`encv`) sample entry and
+/// parses its `sinf` box. Returns `None` when the track isn't encrypted, or
+/// when any box along the way (`minf`, `stbl`, `stsd`, `sinf`, `schm`,
+/// `schi`, `tenc`) is absent.
+fn find_encryption<T: Read + Seek>(mdia: &mut Input<T>) -> Result<Option<Encryption>> {
+    let Some(mut minf) = mdia.quicktime_search_box("minf")? else { return Ok(None) };
+    let Some(mut stbl) = minf.quicktime_search_box("stbl")? else { return Ok(None) };
+    let Some(mut stsd) = stbl.quicktime_search_box("stsd")? else { return Ok(None) };
+
+    stsd.ff(4)?; // version + flags
+    let entry_count = stsd.read_u32(&Endianness::Big)?;
+    for _ in 0..entry_count {
+        let entry_size = stsd.read_u32(&Endianness::Big)? as u64;
+        let format = stsd.read_string(4)?;
+        let body_len = entry_size.checked_sub(8).ok_or_else(|| Error::InvalidData(format!(
+            "sample entry '{}' declares length {} shorter than its 8-byte header", format, entry_size)))?;
+        let mut entry = stsd.section(body_len);
+        stsd.ff(body_len)?;
+
+        let fixed_len = match format.as_str() {
+            "enca" => AUDIO_SAMPLE_ENTRY_FIXED_LEN,
+            "encv" => VIDEO_SAMPLE_ENTRY_FIXED_LEN,
+            _ => continue,
+        };
+        entry.ff(fixed_len)?;
+
+        let Some(mut sinf) = entry.quicktime_search_box("sinf")? else { continue };
+        return parse_sinf(&mut sinf);
+    }
+    Ok(None)
+}
+
+fn parse_sinf<T: Read + Seek>(sinf: &mut Input<T>) -> Result<Option<Encryption>> {
+    let Some(mut schm) = sinf.quicktime_search_box("schm")? else { return Ok(None) };
+    schm.ff(4)?; // version + flags; TODO scheme_uri not parsed when flags bit 0 is set
+    let scheme_type = schm.read_string(4)?;
+    if !matches!(scheme_type.as_str(), "cenc" | "cbc1" | "cens" | "cbcs") {
+        return Err(Error::Unsupported(format!("protection scheme '{}'", scheme_type)));
+    }
+    let mut scheme = [0u8; 4];
+    scheme.copy_from_slice(scheme_type.as_bytes());
+    let version = schm.read_u32(&Endianness::Big)?;
+
+    let Some(mut schi) = sinf.quicktime_search_box("schi")? else { return Ok(None) };
+    let Some(mut tenc) = schi.quicktime_search_box("tenc")? else { return Ok(None) };
+    tenc.ff(4)?; // version + flags
+    tenc.ff(1)?; // reserved
+    tenc.ff(1)?; // reserved (v0) / crypt_byte_block + skip_byte_block (v1)
+    tenc.ff(1)?; // default_isProtected; only the default KID/IV size matter here
+    let per_sample_iv_size = tenc.read_u8()?;
+    let kid_bytes = tenc.read_bytes(16)?;
+    let mut default_kid = [0u8; 16];
+    default_kid.copy_from_slice(&kid_bytes);
+
+    Ok(Some(Encryption { scheme, version, default_kid, per_sample_iv_size }))
+}
+
+pub struct Track {
+    pub track_id: u32,
+    pub duration: u64,
+    pub timescale: u32,
+    pub width: f32,
+    pub height: f32,
+    pub language: String,
+    pub encryption: Option<Encryption>,
+}
+
+impl Track {
+    pub fn duration_seconds(&self) -> f64 {
+        self.duration as f64 / self.timescale as f64
+    }
+}
+
+pub struct Movie {
+    version: u8,
+    creation_time: u64,
+    modification_time: u64,
+    duration: u64,
+    timescale: u32,
+    tracks: Vec<Track>,
+}
+
+impl Movie {
+    pub fn read<T: Read + Seek>(mut input: Input<T>) -> Result<Movie> {
+        let mut moov = input.quicktime_search_box("moov")?
+            .ok_or_else(|| Error::InvalidData("moov box not found".to_string()))?;
+        let mvhd_box = moov.quicktime_search_box("mvhd")?
+            .ok_or_else(|| Error::InvalidData("mvhd box not found".to_string()))?;
+        let mvhd = Mvhd::parse(mvhd_box)?;
+
+        let mut tracks = Vec::new();
+        while let Some(mut trak) = moov.quicktime_search_box("trak")? {
+            let tkhd_box = trak.quicktime_search_box("tkhd")?
+                .ok_or_else(|| Error::InvalidData("tkhd box not found".to_string()))?;
+            let tkhd = Tkhd::parse(tkhd_box)?;
+
+            let mut mdia = trak.quicktime_search_box("mdia")?
+                .ok_or_else(|| Error::InvalidData("mdia box not found".to_string()))?;
+            let mdhd_box = mdia.quicktime_search_box("mdhd")?
+                .ok_or_else(|| Error::InvalidData("mdhd box not found".to_string()))?;
+            let mdhd = Mdhd::parse(mdhd_box)?;
+            let encryption = find_encryption(&mut mdia)?;
+
+            tracks.push(Track {
+                track_id: tkhd.track_id,
+                duration: mdhd.duration,
+                timescale: mdhd.timescale,
+                width: tkhd.width,
+                height: tkhd.height,
+                encryption,
+                language: mdhd.language,
+            });
+        }
+
+        Ok(Movie {
+            version: mvhd.version,
+            creation_time: mvhd.creation_time,
+            modification_time: mvhd.modification_time,
+            duration: mvhd.duration,
+            timescale: mvhd.timescale,
+            tracks,
+        })
+    }
+
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    pub fn creation_time(&self) -> u64 {
+        self.creation_time
+    }
+
+    pub fn modification_time(&self) -> u64 {
+        self.modification_time
+    }
+
+    pub fn duration(&self) -> u64 {
+        self.duration
+    }
+
+    pub fn timescale(&self) -> u32 {
+        self.timescale
+    }
+
+    pub fn duration_seconds(&self) -> f64 {
+        self.duration as f64 / self.timescale as f64
+    }
+
+    pub fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn push_box(buffer: &mut Vec<u8>, box_type: &[u8; 4], body: &[u8]) {
+        let box_length = (8 + body.len()) as u32;
+        buffer.extend_from_slice(&box_length.to_be_bytes());
+        buffer.extend_from_slice(box_type);
+        buffer.extend_from_slice(body);
+    }
+
+    fn mvhd_v0_body(timescale: u32, duration: u32) -> Vec<u8> {
+        let mut body = Vec::new();
+        body.extend_from_slice(&0u32.to_be_bytes()); // version 0 + flags
+        body.extend_from_slice(&0u32.to_be_bytes()); // creation_time
+        body.extend_from_slice(&0u32.to_be_bytes()); // modification_time
+        body.extend_from_slice(&timescale.to_be_bytes());
+        body.extend_from_slice(&duration.to_be_bytes());
+        body
+    }
+
+    fn tkhd_v0_body(track_id: u32, duration: u32, width: u32, height: u32) -> Vec<u8> {
+        let mut body = Vec::new();
+        body.extend_from_slice(&0u32.to_be_bytes()); // version 0 + flags
+        body.extend_from_slice(&0u32.to_be_bytes()); // creation_time
+        body.extend_from_slice(&0u32.to_be_bytes()); // modification_time
+        body.extend_from_slice(&track_id.to_be_bytes());
+        body.extend_from_slice(&0u32.to_be_bytes()); // reserved
+        body.extend_from_slice(&duration.to_be_bytes());
+        body.extend_from_slice(&[0u8; 8]); // reserved
+        body.extend_from_slice(&[0u8; 8]); // layer, alternate_group, volume, reserved
+        body.extend_from_slice(&[0u8; 36]); // matrix
+        body.extend_from_slice(&width.to_be_bytes());
+        body.extend_from_slice(&height.to_be_bytes());
+        body
+    }
+
+    fn mdhd_v0_body(timescale: u32, duration: u32, packed_language: u16) -> Vec<u8> {
+        let mut body = Vec::new();
+        body.extend_from_slice(&0u32.to_be_bytes()); // version 0 + flags
+        body.extend_from_slice(&0u32.to_be_bytes()); // creation_time
+        body.extend_from_slice(&0u32.to_be_bytes()); // modification_time
+        body.extend_from_slice(&timescale.to_be_bytes());
+        body.extend_from_slice(&duration.to_be_bytes());
+        body.extend_from_slice(&packed_language.to_be_bytes());
+        body.extend_from_slice(&0u16.to_be_bytes()); // pre_defined
+        body
+    }
+
+    fn tenc_body(is_protected: u8, per_sample_iv_size: u8, kid: &[u8; 16]) -> Vec<u8> {
+        let mut body = Vec::new();
+        body.extend_from_slice(&0u32.to_be_bytes()); // version 0 + flags
+        body.push(0); // reserved
+        body.push(0); // reserved (v0) / crypt_byte_block + skip_byte_block (v1)
+        body.push(is_protected);
+        body.push(per_sample_iv_size);
+        body.extend_from_slice(kid);
+        body
+    }
+
+    fn schm_body(scheme_type: &[u8; 4], version: u32) -> Vec<u8> {
+        let mut body = Vec::new();
+        body.extend_from_slice(&0u32.to_be_bytes()); // version 0 + flags
+        body.extend_from_slice(scheme_type);
+        body.extend_from_slice(&version.to_be_bytes());
+        body
+    }
+
+    #[test]
+    fn decodes_packed_iso639_language() {
+        // "eng" packed as 5 bits per letter, offset by 0x60
+        let packed = (('e' as u16 - 0x60) << 10) | (('n' as u16 - 0x60) << 5) | ('g' as u16 - 0x60);
+        assert_eq!(decode_language(packed), "eng");
+    }
+
+    #[test]
+    fn reads_movie_with_a_single_track() {
+        let mvhd = mvhd_v0_body(1000, 5000);
+        let tkhd = tkhd_v0_body(1, 4800, 1920 << 16, 1080 << 16);
+        let mdhd = mdhd_v0_body(48000, 240000, 0x15C7); // "eng"
+
+        let mut mdia_body = Vec::new();
+        push_box(&mut mdia_body, b"mdhd", &mdhd);
+
+        let mut trak_body = Vec::new();
+        push_box(&mut trak_body, b"tkhd", &tkhd);
+        push_box(&mut trak_body, b"mdia", &mdia_body);
+
+        let mut moov_body = Vec::new();
+        push_box(&mut moov_body, b"mvhd", &mvhd);
+        push_box(&mut moov_body, b"trak", &trak_body);
+
+        let mut data = Vec::new();
+        push_box(&mut data, b"moov", &moov_body);
+
+        let input = Input::from_bytes(&data).unwrap();
+        let movie = Movie::read(input).unwrap();
+
+        assert_eq!(movie.timescale(), 1000);
+        assert_eq!(movie.duration(), 5000);
+        assert_eq!(movie.duration_seconds(), 5.0);
+
+        let tracks = movie.tracks();
+        assert_eq!(tracks.len(), 1);
+        assert_eq!(tracks[0].track_id, 1);
+        assert_eq!(tracks[0].width, 1920.0);
+        assert_eq!(tracks[0].height, 1080.0);
+        assert_eq!(tracks[0].language, "eng");
+        assert_eq!(tracks[0].duration_seconds(), 5.0);
+        assert!(tracks[0].encryption.is_none());
+    }
+
+    #[test]
+    fn reads_cenc_protection_info_for_an_encrypted_track() {
+        let kid = [0x11u8; 16];
+
+        let mut schi_body = Vec::new();
+        push_box(&mut schi_body, b"tenc", &tenc_body(1, 8, &kid));
+        let mut sinf_body = Vec::new();
+        push_box(&mut sinf_body, b"schm", &schm_body(b"cenc", 1));
+        push_box(&mut sinf_body, b"schi", &schi_body);
+
+        let mut enca_body = Vec::new();
+        enca_body.extend_from_slice(&[0u8; 6]); // reserved
+        enca_body.extend_from_slice(&1u16.to_be_bytes()); // data_reference_index
+        enca_body.extend_from_slice(&[0u8; 20]); // AudioSampleEntry fixed fields
+        push_box(&mut enca_body, b"sinf", &sinf_body);
+
+        let mut stsd_body = Vec::new();
+        stsd_body.extend_from_slice(&0u32.to_be_bytes()); // version + flags
+        stsd_body.extend_from_slice(&1u32.to_be_bytes()); // entry_count
+        push_box(&mut stsd_body, b"enca", &enca_body);
+
+        let mut stbl_body = Vec::new();
+        push_box(&mut stbl_body, b"stsd", &stsd_body);
+        let mut minf_body = Vec::new();
+        push_box(&mut minf_body, b"stbl", &stbl_body);
+
+        let mdhd = mdhd_v0_body(48000, 240000, 0x15C7);
+        let mut mdia_body = Vec::new();
+        push_box(&mut mdia_body, b"mdhd", &mdhd);
+        push_box(&mut mdia_body, b"minf", &minf_body);
+
+        let tkhd = tkhd_v0_body(1, 4800, 0, 0);
+        let mut trak_body = Vec::new();
+        push_box(&mut trak_body, b"tkhd", &tkhd);
+        push_box(&mut trak_body, b"mdia", &mdia_body);
+
+        let mvhd = mvhd_v0_body(1000, 5000);
+        let mut moov_body = Vec::new();
+        push_box(&mut moov_body, b"mvhd", &mvhd);
+        push_box(&mut moov_body, b"trak", &trak_body);
+
+        let mut data = Vec::new();
+        push_box(&mut data, b"moov", &moov_body);
+
+        let input = Input::from_bytes(&data).unwrap();
+        let movie = Movie::read(input).unwrap();
+
+        let encryption = movie.tracks()[0].encryption.as_ref().unwrap();
+        assert_eq!(&encryption.scheme, b"cenc");
+        assert_eq!(encryption.version, 1);
+        assert_eq!(encryption.per_sample_iv_size, 8);
+        assert_eq!(encryption.default_kid, kid);
+    }
+}